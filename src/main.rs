@@ -1,17 +1,20 @@
+use std::collections::HashMap;
 use std::fmt::{Debug, Display, Formatter};
 use std::fs::File;
 use std::io::{Read, Write};
-use std::path::Path;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 use std::time::{Duration, Instant};
 use bitvec::macros::internal::funty::Fundamental;
 use bitvec::vec::BitVec;
+use clap::{Parser, ValueEnum};
 use derive_new::new;
 use hhmmss::Hhmmss;
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 
 const ASCII_ALPH_OFFSET: u8 = 97;
+const WORD_LENGTH: usize = 5;
 
 #[derive(Clone, Eq, PartialEq)]
 struct Word<const N: usize> {
@@ -69,11 +72,71 @@ impl<const N: usize> Display for Word<N> {
 
 const ALL_ALLOWED: u32 = 0b00000011111111111111111111111111;
 
+/// Index (0..26) of the single set bit in a one-hot character mask, as produced by `Word::new`.
+fn letter_index(char_mask: u32) -> usize {
+    char_mask.trailing_zeros() as usize
+}
+
+/// Per-position Wordle colouring of `guess` against `actual`: 0 = grey, 1 = yellow, 2 = green.
+///
+/// Uses the standard two-pass algorithm so repeated letters are scored correctly: greens are
+/// claimed first, then yellows are claimed against what's left of the answer's letter counts.
+fn score_guess<const N: usize>(guess: &Word<N>, actual: &Word<N>) -> [u8; N] {
+    let guess_chars = guess.chars();
+    let actual_chars = actual.chars();
+
+    let mut remaining = [0i8; 26];
+    for c in actual_chars {
+        remaining[letter_index(*c)] += 1;
+    }
+
+    let mut digits = [0u8; N];
+    for i in 0..N {
+        if guess_chars[i] == actual_chars[i] {
+            digits[i] = 2;
+            remaining[letter_index(guess_chars[i])] -= 1;
+        }
+    }
+    for i in 0..N {
+        if digits[i] == 2 {
+            continue;
+        }
+        let idx = letter_index(guess_chars[i]);
+        if remaining[idx] > 0 {
+            digits[i] = 1;
+            remaining[idx] -= 1;
+        }
+    }
+
+    digits
+}
+
+/// Encodes a per-position colouring as a single base-3 integer (digit `i` is the colour of
+/// position `i`), matching the bucketing used by the entropy and minimax strategies.
+fn encode_pattern<const N: usize>(digits: &[u8; N]) -> usize {
+    digits.iter()
+        .enumerate()
+        .fold(0usize, |pattern, (i, &digit)| pattern + digit as usize * 3usize.pow(i as u32))
+}
+
+/// Encodes a guess's colouring against `actual` as a single base-3 integer; see `encode_pattern`.
+fn response_pattern<const N: usize>(guess: &Word<N>, actual: &Word<N>) -> usize {
+    encode_pattern(&score_guess(guess, actual))
+}
+
+/// Inverse of `encode_pattern`: recovers the per-position colouring from a base-3 pattern id.
+fn decode_pattern<const N: usize>(pattern: u8) -> [u8; N] {
+    core::array::from_fn(|i| ((pattern as usize / 3usize.pow(i as u32)) % 3) as u8)
+}
+
 #[derive(Copy, Clone)]
 struct WordMask<const N: usize> {
     char_mask: [u32; N],
     positive_mask: u32,
-    negative_mask: u32
+    /// Lower bound on how many times each letter (indexed a=0..z=25) must appear in the answer.
+    min_count: [u8; 26],
+    /// Upper bound on how many times each letter must appear in the answer.
+    max_count: [u8; 26]
 }
 
 impl<const N: usize> WordMask<N> {
@@ -81,10 +144,11 @@ impl<const N: usize> WordMask<N> {
         WordMask {
             char_mask: [ALL_ALLOWED; N],
             positive_mask: 0,
-            negative_mask: 0
+            min_count: [0; 26],
+            max_count: [N as u8; 26]
         }
     }
-    
+
     fn new_from_guess(guess: &Word<N>, actual: &Word<N>) -> WordMask<N> {
         let mut new = WordMask::new();
         new.apply_guess(guess, actual, false);
@@ -95,91 +159,101 @@ impl<const N: usize> WordMask<N> {
         if verbose {
             println!("Applying guess: {}, actual: {}, to mask \n {:?}", guess, actual, self);
         }
+        let digits = score_guess(guess, actual);
+        self.apply_colors(guess, digits, verbose);
+    }
+
+    /// Applies an already-known per-position colouring (green/yellow/grey) of `guess` to this
+    /// mask. Used both by `apply_guess`, which derives the colouring from a known answer, and by
+    /// `apply_input`, which derives it from feedback typed in by the user.
+    fn apply_colors(&mut self, guess: &Word<N>, digits: [u8; N], verbose: bool) {
         let guess_chars = guess.chars();
-        let actual_chars = actual.chars();
+
         for i in 0..N {
-            if guess_chars[i] == actual_chars[i] {
-                self.char_mask[i] = actual_chars[i];
-                self.positive_mask |= actual_chars[i];
-                if verbose {
-                    println!("Char aligned");
+            match digits[i] {
+                2 => {
+                    self.char_mask[i] = guess_chars[i];
+                    if verbose {
+                        println!("Char aligned");
+                    }
                 }
-            }
-            else if actual_chars.contains(&guess_chars[i]) {
-                self.char_mask[i] = self.char_mask[i] & (! guess_chars[i]);
-                self.positive_mask |= guess_chars[i];
-                if verbose {
-                    println!("Char found");
+                1 => {
+                    self.char_mask[i] = self.char_mask[i] & (! guess_chars[i]);
+                    if verbose {
+                        println!("Char found");
+                    }
                 }
-            }
-            else {
-                self.negative_mask |= guess_chars[i];
-                if verbose {
-                    println!("Char not found - {:#b}", self.negative_mask);
+                _ => {
+                    if verbose {
+                        println!("Char not found - {:#b}", guess_chars[i]);
+                    }
                 }
             }
         }
-    }
-    
-    fn apply_input(&mut self, input: &str) -> bool {
-        todo!();
 
-        let mut char_mask = self.char_mask.clone();
-        let mut all_char_mask = self.positive_mask;
-        
-        if !input.is_ascii() {
-            println!("Input is not ASCII");
-            return false;
+        // Tally how many times each letter was green/yellow vs. grey in this guess so the
+        // per-letter count constraints can be tightened.
+        let mut guessed_count = [0u8; 26];
+        let mut confirmed_count = [0u8; 26];
+        let mut has_grey = [false; 26];
+        for i in 0..N {
+            let idx = letter_index(guess_chars[i]);
+            guessed_count[idx] += 1;
+            if digits[i] != 0 {
+                confirmed_count[idx] += 1;
+                self.positive_mask |= guess_chars[i];
+            }
+            else {
+                has_grey[idx] = true;
+            }
         }
-        
-        let mut skip_next = false;
 
-        let space = " ";
-        let mut i_adjust = 0;
-        for ((i, c), nc) in input.chars().enumerate().zip(input.chars().skip(1).chain(space.chars())) {
-            if skip_next {
-                skip_next = false;
-                i_adjust += 1;
+        for idx in 0..26 {
+            if guessed_count[idx] == 0 {
                 continue;
             }
-            
-            let c = c as u8;
-            if c < ASCII_ALPH_OFFSET {
-                println!("Invalid character: {c}");
-                return false;
+            if confirmed_count[idx] > self.min_count[idx] {
+                self.min_count[idx] = confirmed_count[idx];
             }
-            let c = c - ASCII_ALPH_OFFSET;
-            if c > 25 {
-                println!("Invalid character: {}", c + ASCII_ALPH_OFFSET);
+            // A grey occurrence means the answer has no more of this letter than were
+            // confirmed by the greens/yellows in this same guess.
+            if has_grey[idx] && confirmed_count[idx] < self.max_count[idx] {
+                self.max_count[idx] = confirmed_count[idx];
             }
-            let c = c as u32;
+        }
+    }
 
-            if nc == '!' {
-                skip_next = true;
-                char_mask[i - i_adjust] = 1 << c;
-            }
-            else if nc == '?' {
-                skip_next = true;
-                char_mask[i - i_adjust] = char_mask[i - i_adjust] & (! (1 << c));
-            }
-            else {
-                all_char_mask &= ! (1 << c);
-            }
+    /// Parses a feedback string for `guess` (one `g`/`y`/`b` per letter, e.g. `"gybbb"`) and
+    /// applies it to this mask. Returns the parsed colouring on success, or `None` if `input`
+    /// isn't a valid feedback string, so the caller can re-prompt.
+    fn apply_input(&mut self, guess: &Word<N>, input: &str) -> Option<[u8; N]> {
+        let input = input.trim();
+        if input.chars().count() != N {
+            println!("Feedback must be {} characters long (one of g/y/b per letter)", N);
+            return None;
         }
 
-        self.char_mask = char_mask;
-        self.positive_mask = all_char_mask;
+        let mut digits = [0u8; N];
+        for (i, c) in input.chars().enumerate() {
+            digits[i] = match c.to_ascii_lowercase() {
+                'g' => 2,
+                'y' => 1,
+                'b' => 0,
+                _ => {
+                    println!("Invalid feedback character '{}': expected g (green), y (yellow) or b (grey)", c);
+                    return None;
+                }
+            };
+        }
 
-        true
+        self.apply_colors(guess, digits, false);
+        Some(digits)
     }
 
     fn filter_word(&self, word: &Word<N>) -> bool {
         if (word.all_chars() & self.positive_mask) != self.positive_mask {
             return false
         }
-        if (word.all_chars() & self.negative_mask) != 0 {
-            return false
-        }
 
         let word_chars = word.chars();
 
@@ -189,6 +263,17 @@ impl<const N: usize> WordMask<N> {
             }
         }
 
+        let mut counts = [0u8; 26];
+        for c in word_chars {
+            counts[letter_index(*c)] += 1;
+        }
+
+        for idx in 0..26 {
+            if counts[idx] < self.min_count[idx] || counts[idx] > self.max_count[idx] {
+                return false;
+            }
+        }
+
         true
     }
 }
@@ -214,10 +299,22 @@ impl<const N: usize> Debug for WordMask<N> {
             }
         }
 
-        sb.push_str("\nNegative: ");
+        sb.push_str("\nMin counts: ");
+        for i in 0..26 {
+            if self.min_count[i] > 0 {
+                sb.push(char::from(ASCII_ALPH_OFFSET + i as u8));
+                sb.push('=');
+                sb.push_str(&self.min_count[i].to_string());
+                sb.push(',');
+            }
+        }
+
+        sb.push_str("\nMax counts: ");
         for i in 0..26 {
-            if (self.negative_mask >> i) & 1 != 0 {
+            if self.max_count[i] < N as u8 {
                 sb.push(char::from(ASCII_ALPH_OFFSET + i as u8));
+                sb.push('=');
+                sb.push_str(&self.max_count[i].to_string());
                 sb.push(',');
             }
         }
@@ -226,6 +323,67 @@ impl<const N: usize> Debug for WordMask<N> {
     }
 }
 
+/// Which metric to use when scoring candidate guesses against the remaining answer set.
+#[derive(Copy, Clone, Eq, PartialEq, ValueEnum)]
+enum Strategy {
+    /// Original heuristic: minimise the total number of candidates left across all possible
+    /// answers (sum of remaining-candidate counts).
+    ExpectedRemaining,
+    /// Maximise the Shannon entropy of the guess's response-pattern distribution.
+    Entropy,
+    /// Minimise the size of the largest response-pattern bucket (worst case).
+    Minimax,
+}
+
+/// Command-line front end for the solver: pick word lists, a strategy, and either benchmark
+/// every answer, solve a single answer, or drive the interactive assist mode.
+#[derive(Parser)]
+#[command(author, version, about = "A Wordle solver and solving assistant")]
+struct Cli {
+    /// Answer word length. Must match the compiled `WORD_LENGTH`, since `Word<N>`'s size is
+    /// fixed at compile time - this exists to catch word-list/build mismatches, not to resize.
+    #[arg(long, default_value_t = WORD_LENGTH)]
+    word_length: usize,
+
+    /// Path to the list of valid answers (the words the solver will benchmark/guess against)
+    #[arg(long, default_value = "valid_answers.txt")]
+    answers: PathBuf,
+
+    /// Path to the list of valid guesses (the words the solver is allowed to guess)
+    #[arg(long, default_value = "valid_guesses.txt")]
+    guesses: PathBuf,
+
+    /// Guess-selection strategy
+    #[arg(long, value_enum, default_value_t = Strategy::Entropy)]
+    strategy: Strategy,
+
+    /// Solve for a single given answer instead of benchmarking every answer in the answer list
+    #[arg(long)]
+    answer: Option<String>,
+
+    /// Run the interactive solving assistant instead of self-play
+    #[arg(long)]
+    interactive: bool,
+
+    /// Enforce hard-mode rules: every guess must itself satisfy all clues revealed so far
+    #[arg(long)]
+    hard: bool,
+
+    /// Path to the cached decision tree built by `--build-tree` / consumed by `--use-tree`
+    #[arg(long, default_value = "decision_tree.bin")]
+    tree: PathBuf,
+
+    /// Build (or rebuild) the decision tree for the current word lists and strategy, save it to
+    /// `--tree`, and exit
+    #[arg(long)]
+    build_tree: bool,
+
+    /// Drive solving from the cached decision tree at `--tree` instead of re-searching every
+    /// turn - O(depth) per game once built
+    #[arg(long)]
+    use_tree: bool,
+}
+
 fn get_words<const N: usize, P: AsRef<Path>>(path: P) -> Vec<Word<N>> {
     let mut words = String::new();
     File::open(path).unwrap().read_to_string(&mut words).unwrap();
@@ -233,126 +391,537 @@ fn get_words<const N: usize, P: AsRef<Path>>(path: P) -> Vec<Word<N>> {
 }
 
 
-fn main() {
-    const WORD_LENGTH: usize = 5;
-    let original_valid_answers = get_words::<WORD_LENGTH, _>("valid_answers.txt");
-    let valid_guesses = get_words::<WORD_LENGTH, _>("valid_guesses.txt");
-
-    let start = Instant::now();
-    let mut total_turns = 0;
-
-    for (i, actual_answer) in original_valid_answers.iter().enumerate() {
-        let mut valid_answers = original_valid_answers.clone();
-        let mut current_mask = WordMask::new();
+/// Builds the dense `guesses x answers` response-pattern matrix once up front: cell
+/// `[guess_idx * answers.len() + answer_idx]` is the base-3 response code for that pair. This
+/// lets every turn of every game reuse the same lookups instead of re-deriving patterns.
+fn build_pattern_matrix<const N: usize>(guesses: &[Word<N>], answers: &[Word<N>]) -> Vec<u8> {
+    guesses.par_iter()
+        .flat_map(|guess| answers.iter().map(|answer| response_pattern(guess, answer) as u8).collect::<Vec<_>>())
+        .collect()
+}
 
-        let mut turns = 0;
+/// The fixed inputs a game of solving is played against: the word lists, the precomputed
+/// response matrix, and the settings that shape how a guess is scored. Bundled into one struct
+/// because `pick_best_guess`, `build_decision_tree` and `solve_one` all thread it through
+/// unchanged alongside whatever per-turn state (live answers, accumulated mask) they're working
+/// with.
+struct SolverContext<'a, const N: usize> {
+    valid_guesses: &'a [Word<N>],
+    original_valid_answers: &'a [Word<N>],
+    patterns: &'a [u8],
+    answer_count: usize,
+    bucket_count: usize,
+    strategy: Strategy,
+    hard: bool,
+}
 
-        // println!("Actual: {}", actual_answer);
+impl<'a, const N: usize> SolverContext<'a, N> {
+    fn new(
+        valid_guesses: &'a [Word<N>],
+        original_valid_answers: &'a [Word<N>],
+        patterns: &'a [u8],
+        bucket_count: usize,
+        strategy: Strategy,
+        hard: bool,
+    ) -> SolverContext<'a, N> {
+        SolverContext {
+            valid_guesses,
+            original_valid_answers,
+            patterns,
+            answer_count: original_valid_answers.len(),
+            bucket_count,
+            strategy,
+            hard,
+        }
+    }
+}
 
-        loop {
-            turns += 1;
+/// Picks the index (into the guess list) of the best guess against the current live-answer set,
+/// under `ctx.strategy`. Shared by the self-play benchmark, the interactive assist mode and
+/// decision-tree building so all three drive the solver the same way.
+///
+/// In hard mode, `current_mask` restricts the search to guesses that are themselves legal
+/// follow-ups (every revealed green reused in place, every known-present letter included).
+///
+/// Panics if `live_answers` holds more than one candidate and no guess in `ctx.valid_guesses`
+/// can split it into more than one bucket -
+/// the live set can never shrink past this point, so every caller (self-play, interactive
+/// assist, decision-tree building) would otherwise loop forever instead of making progress.
+fn pick_best_guess<const N: usize>(
+    ctx: &SolverContext<N>,
+    live_answers: &BitVec<usize>,
+    live_count: usize,
+    current_mask: &WordMask<N>,
+) -> usize {
+    // Higher score is always better, regardless of strategy: minimising metrics (remaining-
+    // candidate count, worst-case bucket size) are negated so a single comparison works for
+    // every strategy.
+    let best_result = Mutex::new((f32::NEG_INFINITY, usize::MAX));
+
+    (0..ctx.valid_guesses.len()).into_par_iter().for_each(|guess_idx| {
+        if ctx.hard && !current_mask.filter_word(&ctx.valid_guesses[guess_idx]) {
+            return;
+        }
 
-            let mut best_total_remaining = AtomicUsize::new(usize::MAX);
-            let mut best_guess = Mutex::new(valid_answers[0].clone());
-            let mut second_best_guess = Mutex::new(valid_answers[0].clone());
-            // let mut progress = AtomicUsize::new(0);
-            // let start = Instant::now();
-            // let mut last = Instant::now();
+        let row = &ctx.patterns[guess_idx * ctx.answer_count..(guess_idx + 1) * ctx.answer_count];
 
-            // println!("Working...");
+        let score = match ctx.strategy {
+            Strategy::ExpectedRemaining => {
+                // Every answer in a bucket now filters down to exactly that bucket (the live set
+                // is narrowed by response-pattern equality), so the total remaining-candidate
+                // count across all answers is just the sum of squares.
+                let mut counts = vec![0u32; ctx.bucket_count];
+                for answer_idx in live_answers.iter_ones() {
+                    counts[row[answer_idx] as usize] += 1;
+                }
 
-            valid_guesses.par_iter().for_each(|guess| {
-                let mut total_remaining = 0;
+                // `total_remaining` is a sum of squares of non-negative counts over a non-empty
+                // `live_answers`, so it's always >= 1 here - no zero case to short-circuit on.
+                let total_remaining: u64 = counts.iter().map(|&c| c as u64 * c as u64).sum();
 
-                for answer in &valid_answers {
-                    let mut filter = current_mask.clone();
-                    filter.apply_guess(guess, answer, false);
-                    total_remaining += valid_answers.iter().filter(|w| filter.filter_word(w)).count();
+                -(total_remaining as f32)
+            }
+            Strategy::Entropy => {
+                let mut counts = vec![0u32; ctx.bucket_count];
+                for answer_idx in live_answers.iter_ones() {
+                    counts[row[answer_idx] as usize] += 1;
                 }
 
-                if total_remaining == 0 {
+                let total = live_count as f32;
+                counts.iter()
+                    .filter(|&&c| c > 0)
+                    .map(|&c| {
+                        let p = c as f32 / total;
+                        -p * p.log2()
+                    })
+                    .sum::<f32>()
+            }
+            Strategy::Minimax => {
+                // A guess already known to leave a bucket at least as large as the current best
+                // can never win, so abandon it as soon as any bucket reaches that size.
+                let current_best = best_result.lock().unwrap().0;
+                let prune_at = if current_best == f32::NEG_INFINITY {
+                    u32::MAX
+                } else {
+                    (-current_best) as u32
+                };
+
+                let mut counts = vec![0u32; ctx.bucket_count];
+                let mut pruned = false;
+                for answer_idx in live_answers.iter_ones() {
+                    let pattern = row[answer_idx] as usize;
+                    counts[pattern] += 1;
+                    if counts[pattern] >= prune_at {
+                        pruned = true;
+                        break;
+                    }
+                }
+                if pruned {
                     return;
                 }
 
-                if total_remaining < best_total_remaining.load(Ordering::Acquire) {
-                    let mut best = best_guess.lock().unwrap();
-                    *second_best_guess.lock().unwrap() = best.clone();
-                    *best = guess.clone();
-                    best_total_remaining.store(total_remaining, Ordering::Release);
-                }
+                -(counts.into_iter().max().unwrap_or(0) as f32)
+            }
+        };
 
-                // progress += 1;
-            });
-            //
-            // for guess in &valid_guesses {
-            //     // if Instant::now() - last > Duration::from_secs(5) {
-            //     //     println!(
-            //     //         "Progress: {}/{} | Elapsed: {} | ETA: {} [{:.2}/s]",
-            //     //         progress,
-            //     //         valid_guesses.len(),
-            //     //         (Instant::now() - start).hhmmss(),
-            //     //         (((Instant::now() - start) / progress as u32) * (valid_guesses.len() - progress) as u32).hhmmss(),
-            //     //         (Instant::now() - start).as_secs_f32() / progress as f32
-            //     //     );
-            //     //     last = Instant::now();
-            //     // }
-            //
-            //
-            // }
-
-            let best_guess = best_guess.into_inner().unwrap();
-            // println!("Best word: {} | Average: {:.2} | Second best: {}", &best_guess, best_total_remaining.into_inner() as f32 / valid_answers.len() as f32, second_best_guess.into_inner().unwrap());
-
-            // println!("Remaining words: {}", valid_answers.len());
-            // for (i, word) in valid_answers.iter().enumerate() {
-            //     if i == 10 { break };
-            //     println!("{}", word);
-            // }
-            // if valid_answers.len() > 10 {
-            //     println!("...");
-            // }
-
-            if &best_guess == actual_answer {
-                break;
+        let mut best = best_result.lock().unwrap();
+        if score > best.0 {
+            *best = (score, guess_idx);
+        }
+    });
+
+    let best_idx = best_result.into_inner().unwrap().1;
+    if best_idx == usize::MAX {
+        panic!("No guess satisfies the hard-mode constraint - is the guess list a superset of the answer list?");
+    }
+
+    // Every strategy strictly prefers any split over none (a single bucket scores worst under
+    // all three), so if even the best guess doesn't split `live_answers`, nothing does.
+    if live_count > 1 {
+        let row = &ctx.patterns[best_idx * ctx.answer_count..(best_idx + 1) * ctx.answer_count];
+        let mut seen_pattern = None;
+        let splits = live_answers.iter_ones().any(|answer_idx| {
+            *seen_pattern.get_or_insert(row[answer_idx]) != row[answer_idx]
+        });
+        if !splits {
+            let indistinguishable: Vec<String> = live_answers.iter_ones()
+                .map(|idx| ctx.original_valid_answers[idx].to_string())
+                .collect();
+            panic!(
+                "No guess in the guess list can distinguish between answers: {} - the solver is stuck",
+                indistinguishable.join(", ")
+            );
+        }
+    }
+
+    best_idx
+}
+
+/// Prints `guess` with each letter's background colour set to its feedback: green for correct
+/// position, yellow for present, grey for absent - mirroring the real Wordle board.
+fn print_colored_guess<const N: usize>(guess: &Word<N>, digits: &[u8; N]) {
+    let word = guess.to_string();
+    for (ch, &digit) in word.chars().zip(digits.iter()) {
+        let background = match digit {
+            2 => 42,  // green
+            1 => 43,  // yellow
+            _ => 100, // grey
+        };
+        print!("\x1b[{};30m{}\x1b[0m", background, ch.to_ascii_uppercase());
+    }
+    println!();
+}
+
+/// Interactive solving assistant: the user plays Wordle elsewhere, we suggest the next guess,
+/// they type back the colour feedback, and we narrow the live-answer set and repeat.
+fn run_interactive<const N: usize>(ctx: &SolverContext<N>) {
+    let answer_count = ctx.answer_count;
+    let mut live_answers = BitVec::<usize>::repeat(true, answer_count);
+    let mut live_count = answer_count;
+    let mut current_mask = WordMask::<N>::new();
+
+    loop {
+        let guess_idx = pick_best_guess(ctx, &live_answers, live_count, &current_mask);
+        let guess = &ctx.valid_guesses[guess_idx];
+
+        println!("Suggested guess: {} ({} candidates remaining)", guess, live_count);
+
+        let digits = loop {
+            print!("Feedback (g/y/b per letter, e.g. \"gybbb\"): ");
+            std::io::stdout().flush().unwrap();
+            let mut input = String::new();
+            std::io::stdin().read_line(&mut input).unwrap();
+            if let Some(digits) = current_mask.apply_input(guess, &input) {
+                break digits;
             }
-            current_mask.apply_guess(&best_guess, &actual_answer, false);
-
-            // loop {
-            //     print!("> ");
-            //     std::io::stdout().flush().unwrap();
-            //     let mut input = String::new();
-            //     std::io::stdin().read_line(&mut input).unwrap();
-            //
-            //     if current_mask.apply_input(input.trim()) {
-            //         break;
-            //     }
-            // }
-
-            // println!("New mask: {:?}", current_mask);
-
-            let mut removed =  0;
-            for i in 0..valid_answers.len() {
-                if !current_mask.filter_word(&valid_answers[i - removed]) {
-                    valid_answers.remove(i - removed);
-                    removed += 1;
-                }
+        };
+
+        print_colored_guess(guess, &digits);
+
+        if digits.iter().all(|&digit| digit == 2) {
+            println!("Solved!");
+            return;
+        }
+
+        let pattern = encode_pattern(&digits);
+        let row = &ctx.patterns[guess_idx * answer_count..(guess_idx + 1) * answer_count];
+        for answer_idx in 0..answer_count {
+            if live_answers[answer_idx] && row[answer_idx] as usize != pattern {
+                live_answers.set(answer_idx, false);
             }
+        }
+        live_count = live_answers.count_ones();
 
-            if valid_answers.len() == 1 {
-                if &valid_answers[0] != actual_answer {
-                    panic!("Final option: {} does not match actual {}", &valid_answers[0], actual_answer);
-                }
-                else {
-                    turns += 1;
-                    break;
-                }
+        if live_count == 0 {
+            println!("No remaining candidates match that feedback - double check what you entered.");
+            return;
+        }
+        if live_count == 1 {
+            let only = live_answers.iter_ones().next().unwrap();
+            println!("The answer must be: {}", ctx.original_valid_answers[only]);
+            return;
+        }
+    }
+}
+
+/// One node of a precomputed decision tree: the guess to make, and which node to move to next
+/// for each possible response pattern. A leaf (empty `children`) means `guess` is the answer.
+///
+/// Stores `guess` as its textual form rather than `Word<N>` so the tree can be serialized
+/// without threading the word-length const generic through the file format.
+#[derive(Clone, Serialize, Deserialize)]
+struct DecisionNode {
+    guess: String,
+    children: HashMap<u8, DecisionNode>,
+}
+
+/// Builds the decision tree for `live_answers`: picks the best guess for this candidate set,
+/// then recurses on the subset left by each distinct response pattern it can produce.
+///
+/// `memo` caches nodes by their candidate set so games that converge on the same remaining
+/// answers (common once a few letters are pinned down) are only solved once. In hard mode,
+/// caching is skipped instead: `current_mask` (not just `live_answers`) determines which
+/// guesses are legal, and two different paths can reach the same candidate set with different
+/// accumulated masks, so a `live_answers`-only cache key would be unsound there.
+fn build_decision_tree<const N: usize>(
+    ctx: &SolverContext<N>,
+    current_mask: WordMask<N>,
+    live_answers: BitVec<usize>,
+    memo: &mut HashMap<BitVec<usize>, DecisionNode>,
+) -> DecisionNode {
+    if !ctx.hard {
+        if let Some(cached) = memo.get(&live_answers) {
+            return cached.clone();
+        }
+    }
+
+    let answer_count = ctx.answer_count;
+    let live_count = live_answers.count_ones();
+
+    // With one candidate left, every guess ties on score (it uniquely disambiguates a singleton),
+    // so `pick_best_guess` has no reason to favour the answer itself - commit to it directly,
+    // matching the live_count == 1 shortcut `solve_one` takes.
+    if live_count == 1 {
+        let answer_idx = live_answers.iter_ones().next().unwrap();
+        return DecisionNode { guess: ctx.original_valid_answers[answer_idx].to_string(), children: HashMap::new() };
+    }
+
+    let guess_idx = pick_best_guess(ctx, &live_answers, live_count, &current_mask);
+    let guess = &ctx.valid_guesses[guess_idx];
+
+    let row = &ctx.patterns[guess_idx * answer_count..(guess_idx + 1) * answer_count];
+
+    // `pick_best_guess` already panics if `guess` can't split `live_answers`, so every bucket
+    // here is guaranteed to be a strict, non-empty subset of it.
+    let mut buckets: HashMap<u8, BitVec<usize>> = HashMap::new();
+    for answer_idx in live_answers.iter_ones() {
+        buckets.entry(row[answer_idx])
+            .or_insert_with(|| BitVec::<usize>::repeat(false, answer_count))
+            .set(answer_idx, true);
+    }
+
+    let mut children = HashMap::new();
+    for (pattern, subset) in buckets {
+        let mut child_mask = current_mask;
+        child_mask.apply_colors(guess, decode_pattern::<N>(pattern), false);
+
+        let child = build_decision_tree(ctx, child_mask, subset, memo);
+        children.insert(pattern, child);
+    }
+
+    let node = DecisionNode { guess: guess.to_string(), children };
+    if !ctx.hard {
+        memo.insert(live_answers, node.clone());
+    }
+    node
+}
+
+/// Walks the decision tree to solve for `actual_answer`, returning the number of turns taken.
+fn solve_with_tree<const N: usize>(tree: &DecisionNode, actual_answer: &Word<N>) -> usize {
+    let mut node = tree;
+    let mut turns = 0;
+
+    loop {
+        turns += 1;
+        let guess = Word::<N>::new(&node.guess);
+
+        if &guess == actual_answer {
+            return turns;
+        }
+
+        let pattern = response_pattern(&guess, actual_answer) as u8;
+        node = node.children.get(&pattern)
+            .unwrap_or_else(|| panic!("Decision tree has no branch for pattern {} after guessing {} - is the tree stale?", pattern, guess));
+    }
+}
+
+/// Interactive assist mode driven entirely by a precomputed decision tree: every suggestion is
+/// an O(depth) lookup instead of a fresh search.
+fn run_interactive_with_tree<const N: usize>(tree: &DecisionNode) {
+    let mut node = tree;
+
+    loop {
+        let guess = Word::<N>::new(&node.guess);
+        println!("Suggested guess: {}", guess);
+
+        // `WordMask` isn't needed to pick the next guess here, only to parse the feedback string.
+        let mut scratch_mask = WordMask::<N>::new();
+        let digits = loop {
+            print!("Feedback (g/y/b per letter, e.g. \"gybbb\"): ");
+            std::io::stdout().flush().unwrap();
+            let mut input = String::new();
+            std::io::stdin().read_line(&mut input).unwrap();
+            if let Some(digits) = scratch_mask.apply_input(&guess, &input) {
+                break digits;
+            }
+        };
+
+        print_colored_guess(&guess, &digits);
+
+        if digits.iter().all(|&digit| digit == 2) {
+            println!("Solved!");
+            return;
+        }
+
+        let pattern = encode_pattern(&digits) as u8;
+        match node.children.get(&pattern) {
+            Some(child) => node = child,
+            None => {
+                println!("Decision tree has no branch for that feedback - it may be stale, try rebuilding with --build-tree.");
+                return;
+            }
+        }
+    }
+}
+
+/// Prints the average, worst-case and per-turn-count distribution of a completed benchmark run.
+fn print_benchmark_summary(total_turns: usize, worst_turns: usize, turn_counts: &[usize], answer_count: usize) {
+    println!("Average turns: {:.3}", total_turns as f32 / answer_count as f32);
+    println!("Worst-case turns: {}", worst_turns);
+    print!("Turn distribution:");
+    for (turns, count) in turn_counts.iter().enumerate() {
+        if *count > 0 {
+            print!(" {}={}", turns, count);
+        }
+    }
+    println!();
+}
+
+/// Solves for `original_valid_answers[actual_idx]` from scratch, returning the number of turns
+/// taken. Shared by the benchmark-every-answer and solve-a-single-answer CLI modes.
+fn solve_one<const N: usize>(ctx: &SolverContext<N>, actual_idx: usize) -> usize {
+    let answer_count = ctx.answer_count;
+    let actual_answer = &ctx.original_valid_answers[actual_idx];
+
+    // The live candidate set, indexed the same way as `original_valid_answers`/`patterns`.
+    let mut live_answers = BitVec::<usize>::repeat(true, answer_count);
+    let mut live_count = answer_count;
+    let mut current_mask = WordMask::new();
+
+    let mut turns = 0;
+
+    loop {
+        turns += 1;
+
+        let best_guess_idx = pick_best_guess(ctx, &live_answers, live_count, &current_mask);
+        let best_guess = &ctx.valid_guesses[best_guess_idx];
+
+        if best_guess == actual_answer {
+            break;
+        }
+        current_mask.apply_guess(best_guess, actual_answer, false);
+
+        // Narrow the live set to the answers consistent with the pattern `best_guess` actually
+        // produced, i.e. intersect with the matrix row for this turn's answer.
+        let row = &ctx.patterns[best_guess_idx * answer_count..(best_guess_idx + 1) * answer_count];
+        let actual_pattern = row[actual_idx];
+        for answer_idx in 0..answer_count {
+            if live_answers[answer_idx] && row[answer_idx] != actual_pattern {
+                live_answers.set(answer_idx, false);
+            }
+        }
+        live_count = live_answers.count_ones();
+
+        if live_count == 1 {
+            let remaining_idx = live_answers.iter_ones().next().unwrap();
+            if remaining_idx != actual_idx {
+                panic!("Final option: {} does not match actual {}", ctx.original_valid_answers[remaining_idx], actual_answer);
+            }
+            else {
+                turns += 1;
+                break;
+            }
+        }
+    }
+
+    turns
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    if cli.word_length != WORD_LENGTH {
+        eprintln!(
+            "--word-length {} was given, but this build is compiled for length {} (Word<N> is fixed at compile time) - rebuild with a different WORD_LENGTH to change it.",
+            cli.word_length, WORD_LENGTH
+        );
+        std::process::exit(1);
+    }
+
+    let bucket_count = 3usize.pow(WORD_LENGTH as u32);
+
+    let original_valid_answers = get_words::<WORD_LENGTH, _>(&cli.answers);
+    let valid_guesses = get_words::<WORD_LENGTH, _>(&cli.guesses);
+    let answer_count = original_valid_answers.len();
+
+    if cli.build_tree {
+        let patterns = build_pattern_matrix(&valid_guesses, &original_valid_answers);
+
+        let ctx = SolverContext::new(&valid_guesses, &original_valid_answers, &patterns, bucket_count, cli.strategy, cli.hard);
+        let live_answers = BitVec::<usize>::repeat(true, answer_count);
+        let mut memo = HashMap::new();
+        let tree = build_decision_tree(&ctx, WordMask::new(), live_answers, &mut memo);
+
+        let bytes = bincode::serialize(&tree).expect("failed to serialize decision tree");
+        std::fs::write(&cli.tree, bytes).unwrap_or_else(|e| panic!("failed to write {}: {}", cli.tree.display(), e));
+        println!("Built decision tree ({} cached nodes) and saved it to {}", memo.len(), cli.tree.display());
+        return;
+    }
+
+    if cli.use_tree {
+        if cli.hard {
+            eprintln!("--hard has no effect with --use-tree: hard-mode is baked into the tree at --build-tree time, not applied at replay time. Rebuild with --build-tree --hard if you need a hard-mode tree.");
+        }
+
+        let bytes = std::fs::read(&cli.tree).unwrap_or_else(|e| panic!("failed to read {}: {}", cli.tree.display(), e));
+        let tree: DecisionNode = bincode::deserialize(&bytes).expect("failed to deserialize decision tree");
+
+        if cli.interactive {
+            run_interactive_with_tree::<WORD_LENGTH>(&tree);
+            return;
+        }
+
+        if let Some(answer) = &cli.answer {
+            let actual_answer = Word::<WORD_LENGTH>::new(answer);
+            let turns = solve_with_tree(&tree, &actual_answer);
+            println!("Solved \"{}\" in {} turns", answer, turns);
+            return;
+        }
+
+        let mut total_turns = 0;
+        let mut worst_turns = 0;
+        let mut turn_counts: Vec<usize> = Vec::new();
+
+        for actual_answer in &original_valid_answers {
+            let turns = solve_with_tree(&tree, actual_answer);
+
+            total_turns += turns;
+            worst_turns = worst_turns.max(turns);
+            if turn_counts.len() <= turns {
+                turn_counts.resize(turns + 1, 0);
             }
+            turn_counts[turns] += 1;
         }
 
+        print_benchmark_summary(total_turns, worst_turns, &turn_counts, answer_count);
+        return;
+    }
+
+    let patterns = build_pattern_matrix(&valid_guesses, &original_valid_answers);
+    let ctx = SolverContext::new(&valid_guesses, &original_valid_answers, &patterns, bucket_count, cli.strategy, cli.hard);
+
+    if cli.interactive {
+        run_interactive(&ctx);
+        return;
+    }
+
+    if let Some(answer) = &cli.answer {
+        let actual_answer = Word::<WORD_LENGTH>::new(answer);
+        let actual_idx = original_valid_answers.iter().position(|w| w == &actual_answer)
+            .unwrap_or_else(|| panic!("\"{}\" is not in the answer list", answer));
+
+        let turns = solve_one(&ctx, actual_idx);
+        println!("Solved \"{}\" in {} turns", answer, turns);
+        return;
+    }
+
+    let start = Instant::now();
+    let mut total_turns = 0;
+    let mut worst_turns = 0;
+    let mut turn_counts: Vec<usize> = Vec::new();
+
+    for i in 0..answer_count {
+        let turns = solve_one(&ctx, i);
+
         total_turns += turns;
+        worst_turns = worst_turns.max(turns);
+        if turn_counts.len() <= turns {
+            turn_counts.resize(turns + 1, 0);
+        }
+        turn_counts[turns] += 1;
 
-        println!("Current average: {:.2} | Elapsed: {} | ETA: {}", total_turns as f32 / (i + 1) as f32, (Instant::now() - start).hhmmss(), (((Instant::now() - start) / (i + 1) as u32) * ((original_valid_answers.len() - (i + 1)) as u32)).hhmmss())
+        println!("Current average: {:.2} | Elapsed: {} | ETA: {}", total_turns as f32 / (i + 1) as f32, (Instant::now() - start).hhmmss(), (((Instant::now() - start) / (i + 1) as u32) * ((answer_count - (i + 1)) as u32)).hhmmss())
     }
 
-    println!("Average turns: {:.3}", total_turns as f32 / original_valid_answers.len() as f32);
+    print_benchmark_summary(total_turns, worst_turns, &turn_counts, answer_count);
 }